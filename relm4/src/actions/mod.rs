@@ -1,10 +1,14 @@
 //! Action utility.
 
 use gtk::gio;
+use gtk::glib;
 use gtk::glib::FromVariant;
 use gtk::prelude::{ActionExt, ActionMapExt, StaticVariantType, ToVariant};
 
+use rhai::{Dynamic, Engine};
+
 use std::marker::PhantomData;
+use std::rc::Rc;
 
 /// Type safe traits for interacting with actions.
 pub mod traits;
@@ -222,6 +226,39 @@ impl<GroupName: ActionGroupName> RelmActionGroup<GroupName> {
             group: gio::SimpleActionGroup::new(),
         }
     }
+
+    /// Register an action named `name` whose `activate` callback invokes the script
+    /// function `fn_name` in `engine` instead of a compiled Rust closure.
+    ///
+    /// The action's target [`glib::Variant`], if any, is converted to `engine`'s
+    /// dynamic value type and passed as the function's only argument. This lets a
+    /// plugin-style menu call into a script without recompiling the application.
+    ///
+    /// The action is registered with parameter type [`glib::VariantTy::VARIANT`]
+    /// ("v") rather than a concrete type, since the target can be any scalar the
+    /// script understands; per GVariant convention that means the payload arrives
+    /// wrapped one layer deep, which [`variant_to_dynamic`] unwraps.
+    pub fn add_scripted_action(&self, name: &str, fn_name: &str, engine: RelmScriptEngine) {
+        let action = gio::SimpleAction::new(name, Some(glib::VariantTy::VARIANT));
+        let fn_name = fn_name.to_string();
+
+        action.connect_activate(move |_action, variant| {
+            let arg = variant
+                .map(|variant| variant_to_dynamic(variant.clone()))
+                .unwrap_or(Dynamic::UNIT);
+            let mut scope = rhai::Scope::new();
+
+            if let Err(error) =
+                engine
+                    .engine
+                    .call_fn::<Dynamic>(&mut scope, &engine.ast, &fn_name, (arg,))
+            {
+                glib::g_warning!("relm4", "scripted action `{}` failed: {}", fn_name, error);
+            }
+        });
+
+        self.group.add_action(&action);
+    }
 }
 
 impl<GroupName: ActionGroupName> Default for RelmActionGroup<GroupName> {
@@ -229,3 +266,158 @@ impl<GroupName: ActionGroupName> Default for RelmActionGroup<GroupName> {
         Self::new()
     }
 }
+
+/// A script compiled once and shared by every [`RelmActionGroup::add_scripted_action`]
+/// that should call into it, so the source isn't re-parsed per action.
+#[derive(Debug, Clone)]
+pub struct RelmScriptEngine {
+    engine: Rc<Engine>,
+    ast: Rc<rhai::AST>,
+}
+
+impl RelmScriptEngine {
+    /// Compile `source` with a fresh [`rhai::Engine`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `source` fails to parse.
+    pub fn compile(source: &str) -> Result<Self, Box<rhai::EvalAltResult>> {
+        let engine = Engine::new();
+        let ast = engine.compile(source)?;
+
+        Ok(Self {
+            engine: Rc::new(engine),
+            ast: Rc::new(ast),
+        })
+    }
+}
+
+/// Build a [`gio::Menu`] from a menu description evaluated by `engine`.
+///
+/// `engine`'s script is expected to expose a `menu()` function returning an array
+/// of objects with a `label` and `action` field, and an optional `target` field,
+/// which are turned into [`gio::MenuItem`]s the same way
+/// [`RelmAction::to_menu_item`] and [`RelmAction::to_menu_item_with_target_value`]
+/// do for statically compiled actions. This makes the application menu (and, via
+/// [`RelmActionGroup::add_scripted_action`], its behavior) fully data-driven.
+///
+/// # Errors
+///
+/// Returns an error if the `menu` function is missing or fails to evaluate, or if
+/// an element of the array it returns is not an object map.
+pub fn menu_from_script(engine: &RelmScriptEngine) -> Result<gio::Menu, Box<rhai::EvalAltResult>> {
+    let mut scope = rhai::Scope::new();
+    let items: rhai::Array = engine
+        .engine
+        .call_fn(&mut scope, &engine.ast, "menu", ())?;
+
+    let menu = gio::Menu::new();
+    for item in items {
+        let entry = item.try_cast::<rhai::Map>().ok_or_else(|| {
+            Box::new(rhai::EvalAltResult::ErrorRuntime(
+                Dynamic::from("`menu()` must return an array of object maps"),
+                rhai::Position::NONE,
+            ))
+        })?;
+        let label = entry
+            .get("label")
+            .cloned()
+            .unwrap_or_default()
+            .into_string()
+            .unwrap_or_default();
+        let action = entry
+            .get("action")
+            .cloned()
+            .unwrap_or_default()
+            .into_string()
+            .unwrap_or_default();
+
+        let menu_item = if let Some(target) = entry.get("target") {
+            let menu_item = gio::MenuItem::new(Some(&label), None);
+            menu_item.set_action_and_target_value(
+                Some(&action),
+                Some(&dynamic_to_variant(target.clone())),
+            );
+            menu_item
+        } else {
+            gio::MenuItem::new(Some(&label), Some(&action))
+        };
+
+        menu.append_item(&menu_item);
+    }
+
+    Ok(menu)
+}
+
+/// Convert a [`glib::Variant`] holding one of the common scalar types to a script
+/// [`Dynamic`] value, falling back to [`Dynamic::UNIT`] for anything else.
+///
+/// `variant` is the "v"-typed container [`RelmActionGroup::add_scripted_action`]
+/// registers its action with, so the concrete scalar is wrapped one layer deep;
+/// [`Variant::as_variant`](glib::Variant::as_variant) unwraps it before the scalar
+/// `get`s below have anything to match against.
+fn variant_to_dynamic(variant: glib::Variant) -> Dynamic {
+    let variant = variant.as_variant().unwrap_or(variant);
+
+    if let Some(value) = variant.get::<bool>() {
+        Dynamic::from(value)
+    } else if let Some(value) = variant.get::<i64>() {
+        Dynamic::from(value)
+    } else if let Some(value) = variant.get::<f64>() {
+        Dynamic::from(value)
+    } else if let Some(value) = variant.get::<String>() {
+        Dynamic::from(value)
+    } else {
+        Dynamic::UNIT
+    }
+}
+
+/// Convert a script [`Dynamic`] value back to a [`glib::Variant`], the inverse of
+/// [`variant_to_dynamic`].
+///
+/// The result is wrapped one layer deep (via the blanket `ToVariant` impl on
+/// [`glib::Variant`] itself) so its type is "v", matching the parameter type
+/// [`RelmActionGroup::add_scripted_action`] registers its action with; passing a
+/// concrete-typed variant as the target of such an action is rejected by GIO.
+fn dynamic_to_variant(value: Dynamic) -> glib::Variant {
+    let variant = if let Some(value) = value.clone().try_cast::<bool>() {
+        value.to_variant()
+    } else if let Some(value) = value.clone().try_cast::<i64>() {
+        value.to_variant()
+    } else if let Some(value) = value.clone().try_cast::<f64>() {
+        value.to_variant()
+    } else if let Some(value) = value.clone().try_cast::<String>() {
+        value.to_variant()
+    } else {
+        ().to_variant()
+    };
+
+    variant.to_variant()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    // Exercises `variant_to_dynamic`/`dynamic_to_variant` the same way a real menu
+    // item does: through an actual `"v"`-typed `gio::SimpleAction::activate` call,
+    // rather than calling the conversion functions directly.
+    #[test]
+    fn variant_round_trips_through_activate() {
+        let action = gio::SimpleAction::new("test", Some(glib::VariantTy::VARIANT));
+
+        let seen = Rc::new(RefCell::new(Dynamic::UNIT));
+        let seen_clone = Rc::clone(&seen);
+        action.connect_activate(move |_action, variant| {
+            let arg = variant
+                .map(|variant| variant_to_dynamic(variant.clone()))
+                .unwrap_or(Dynamic::UNIT);
+            *seen_clone.borrow_mut() = arg;
+        });
+
+        action.activate(Some(&dynamic_to_variant(Dynamic::from(42_i64))));
+
+        assert_eq!(seen.borrow().clone().as_int().unwrap(), 42);
+    }
+}
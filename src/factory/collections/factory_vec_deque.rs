@@ -1,3 +1,5 @@
+use futures::stream::{Stream, StreamExt};
+use gtk::glib;
 use gtk::glib::Sender;
 
 use std::cell::RefCell;
@@ -41,6 +43,11 @@ impl DynamicIndex {
         *self.inner.borrow_mut() -= 1;
     }
 
+    #[doc(hidden)]
+    fn set(&self, index: usize) {
+        *self.inner.borrow_mut() = index;
+    }
+
     #[doc(hidden)]
     fn new(index: usize) -> Self {
         DynamicIndex {
@@ -49,73 +56,123 @@ impl DynamicIndex {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum ChangeType {
-    Unchanged,
-    Add,
-    Remove,
-    Recreate,
-    Update,
+/// An opaque, stable identifier for an element stored in a [`FactoryVecDeque`].
+///
+/// Unlike [`DynamicIndex`], a [`FactoryKey`] never changes as items are inserted,
+/// removed or reordered around it, so it is safe to hold on to across multiple
+/// [`Factory::generate`] calls to refer back to "the same logical item".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FactoryKey(u32);
+
+#[derive(Debug)]
+struct IndexedData<T> {
+    inner: T,
+    index: Rc<DynamicIndex>,
+    key: FactoryKey,
 }
 
-impl ChangeType {
-    fn apply(&mut self, other: ChangeType) {
-        match self {
-            ChangeType::Unchanged => {
-                *self = other;
-            }
-            ChangeType::Update => {
-                if other != ChangeType::Unchanged {
-                    *self = other;
-                }
-            }
-            ChangeType::Add | ChangeType::Recreate => {
-                if other == ChangeType::Remove {
-                    *self = ChangeType::Remove;
-                } else if other != ChangeType::Update {
-                    panic!(
-                        "Logical error in change tracking. Unexpected change: {:?} <- {:?}",
-                        self, other
-                    );
-                }
-            }
-            ChangeType::Remove => {
-                if other == ChangeType::Add {
-                    *self = ChangeType::Recreate;
-                } else {
-                    panic!(
-                        "Logical error in change tracking. Unexpected change: {:?} <- {:?}",
-                        self, other
-                    );
-                }
-            }
+impl<T> IndexedData<T> {
+    fn new(data: T, index: usize, key: FactoryKey) -> Self {
+        let index = Rc::new(DynamicIndex::new(index));
+        IndexedData {
+            inner: data,
+            index,
+            key,
         }
     }
 }
 
+/// A contiguous, ordered group of root widgets produced by a single factory item.
+///
+/// Most [`FactoryPrototype`]s produce exactly one root widget, but some need to
+/// insert a run of sibling roots (or none at all) as a single unit, such as a
+/// header/body pair for one list entry. [`FactoryPrototype::get_root`] returns one
+/// of these so `generate` can insert, update and remove the whole run atomically
+/// while still keeping a single [`DynamicIndex`] per item.
 #[derive(Debug)]
-struct Change {
-    ty: ChangeType,
-    index: usize,
-}
+pub struct FactoryFragment<Root>(Vec<Root>);
+
+impl<Root> FactoryFragment<Root> {
+    /// Wrap a single root widget in a one-element fragment.
+    pub fn single(root: Root) -> Self {
+        FactoryFragment(vec![root])
+    }
+
+    /// Wrap zero or more sibling root widgets in a fragment.
+    pub fn new(roots: impl IntoIterator<Item = Root>) -> Self {
+        FactoryFragment(roots.into_iter().collect())
+    }
+
+    /// An empty fragment that contributes no widgets to the view.
+    pub fn empty() -> Self {
+        FactoryFragment(Vec::new())
+    }
 
-impl Change {
-    fn new(index: usize, ty: ChangeType) -> Self {
-        Change { index, ty }
+    /// The roots in this fragment, in display order.
+    pub fn roots(&self) -> &[Root] {
+        &self.0
     }
 }
 
+/// A single entry in the ordered, `widgets`-only change log of a [`FactoryVecDeque`].
+///
+/// Every mutating method appends one of these (`swap` appends two) instead of folding the
+/// change into a per-index flag, so [`Factory::generate`] only has to replay the
+/// log in order against the `widgets` queue and the [`FactoryView`] rather than
+/// reconstructing per-index state from overlapping `Add`/`Remove`/`Update` markers.
+/// Unlike [`FactoryDiff`], `self.data` has already been mutated by the time one of
+/// these is recorded, so indices only ever need to describe where to move widgets
+/// around, never what value to store.
+///
+/// `Move` relies on [`FactoryListView::move_front`] and [`FactoryListView::move_after`]
+/// to relocate an existing root widget, so reordering never destroys and rebuilds it.
+///
+/// Indices are always positions in the [`FactoryVecDeque`] as it stood at the moment
+/// the diff was recorded; later mutations shift already-queued indices (see
+/// `shift_diffs_for_insert`/`shift_diffs_for_remove`/`shift_diffs_for_move`) to keep
+/// them pointing at the same logical item.
 #[derive(Debug)]
-struct IndexedData<T> {
-    inner: T,
-    index: Rc<DynamicIndex>,
+enum WidgetDiff {
+    /// The whole contents of the [`FactoryVecDeque`] were replaced.
+    Replace,
+    /// A new element was inserted at `index`.
+    InsertAt(usize),
+    /// The element at `index` was modified in place.
+    UpdateAt(usize),
+    /// The element that used to live at `index` was removed.
+    RemoveAt(usize),
+    /// The last element was removed.
+    Pop,
+    /// Every element was removed.
+    Clear,
+    /// The element at `old_index` now lives at `new_index`, without being recreated.
+    Move { old_index: usize, new_index: usize },
 }
 
-impl<T> IndexedData<T> {
-    fn new(data: T, index: usize) -> Self {
-        let index = Rc::new(DynamicIndex::new(index));
-        IndexedData { inner: data, index }
-    }
+/// A single requested mutation of a [`FactoryVecDeque`]'s data, carrying whatever
+/// value the mutation needs.
+///
+/// This is what [`FactoryVecDeque::apply_diff`] consumes: a caller can describe a
+/// complete change to the collection as one value, since the value it needs to
+/// insert or update with travels along with the diff itself.
+#[derive(Debug)]
+pub enum FactoryDiff<Data> {
+    /// Replace the entire contents of the collection with `values`.
+    Replace(Vec<Data>),
+    /// Insert `data` at `index`.
+    InsertAt(usize, Data),
+    /// Append `data` at the end.
+    Push(Data),
+    /// Replace the element at `index` with `data`.
+    UpdateAt(usize, Data),
+    /// Remove the element at `index`.
+    RemoveAt(usize),
+    /// Remove the last element.
+    Pop,
+    /// Remove every element.
+    Clear,
+    /// Move the element at `old_index` so it ends up at `new_index`.
+    Move { old_index: usize, new_index: usize },
 }
 
 /// A container similar to [`VecDeque`] that implements [`Factory`].
@@ -126,7 +183,8 @@ where
 {
     data: VecDeque<IndexedData<Data>>,
     widgets: RefCell<VecDeque<Data::Widgets>>,
-    changes: RefCell<Vec<Change>>,
+    diffs: RefCell<Vec<WidgetDiff>>,
+    next_key: u32,
 }
 
 impl<Data> FactoryVecDeque<Data>
@@ -138,44 +196,151 @@ where
         FactoryVecDeque {
             data: VecDeque::new(),
             widgets: RefCell::new(VecDeque::new()),
-            changes: RefCell::new(Vec::new()),
+            diffs: RefCell::new(Vec::new()),
+            next_key: 0,
         }
     }
 
+    /// Allocate the next, never-reused [`FactoryKey`].
+    fn next_key(&mut self) -> FactoryKey {
+        let key = FactoryKey(self.next_key);
+        self.next_key += 1;
+        key
+    }
+
     /// Insert an element at the end of a [`FactoryVecDeque`].
     pub fn push_back(&mut self, data: Data) {
         let index = self.data.len();
-        let data = IndexedData::new(data, index);
-        self.add_change(Change::new(index, ChangeType::Add));
-        self.data.push_back(data);
+        let key = self.next_key();
+        self.data.push_back(IndexedData::new(data, index, key));
+        self.diffs.borrow_mut().push(WidgetDiff::InsertAt(index));
     }
 
     /// Remove an element at the end of a [`FactoryVecDeque`].
     pub fn pop_back(&mut self) -> Option<Data> {
         let data = self.data.pop_back();
-        let index = self.data.len();
-        self.add_change(Change::new(index, ChangeType::Remove));
+        if data.is_some() {
+            self.diffs.borrow_mut().push(WidgetDiff::Pop);
+        }
         data.map(|data| data.inner)
     }
 
+    /// Adjust the indices embedded in already-queued diffs so each one still refers
+    /// to the same logical item once a new element is inserted at `at`.
+    ///
+    /// Without this, a diff recorded by an earlier call (e.g. [`get_mut`](Self::get_mut))
+    /// would go stale the moment a later call inserts in front of the item it names.
+    fn shift_diffs_for_insert(&self, at: usize) {
+        for diff in self.diffs.borrow_mut().iter_mut() {
+            match diff {
+                WidgetDiff::InsertAt(index)
+                | WidgetDiff::UpdateAt(index)
+                | WidgetDiff::RemoveAt(index) => {
+                    if *index >= at {
+                        *index += 1;
+                    }
+                }
+                WidgetDiff::Move {
+                    old_index,
+                    new_index,
+                } => {
+                    if *old_index >= at {
+                        *old_index += 1;
+                    }
+                    if *new_index >= at {
+                        *new_index += 1;
+                    }
+                }
+                WidgetDiff::Replace | WidgetDiff::Pop | WidgetDiff::Clear => {}
+            }
+        }
+    }
+
+    /// Adjust the indices embedded in already-queued diffs so each one still refers
+    /// to the same logical item once the element at `at` is removed.
+    fn shift_diffs_for_remove(&self, at: usize) {
+        for diff in self.diffs.borrow_mut().iter_mut() {
+            match diff {
+                WidgetDiff::InsertAt(index)
+                | WidgetDiff::UpdateAt(index)
+                | WidgetDiff::RemoveAt(index) => {
+                    if *index > at {
+                        *index -= 1;
+                    }
+                }
+                WidgetDiff::Move {
+                    old_index,
+                    new_index,
+                } => {
+                    if *old_index > at {
+                        *old_index -= 1;
+                    }
+                    if *new_index > at {
+                        *new_index -= 1;
+                    }
+                }
+                WidgetDiff::Replace | WidgetDiff::Pop | WidgetDiff::Clear => {}
+            }
+        }
+    }
+
+    /// Adjust the indices embedded in already-queued diffs so each one still refers
+    /// to the same logical item once the element at `from` is relocated to `to`,
+    /// shifting everything between them, `to` included and `from` excluded, by one
+    /// the same way `move_to` itself does.
+    fn shift_diffs_for_move(&self, from: usize, to: usize) {
+        let remap = |index: usize| -> usize {
+            if index == from {
+                to
+            } else if from < to && index > from && index <= to {
+                index - 1
+            } else if to < from && index >= to && index < from {
+                index + 1
+            } else {
+                index
+            }
+        };
+
+        for diff in self.diffs.borrow_mut().iter_mut() {
+            match diff {
+                WidgetDiff::InsertAt(index)
+                | WidgetDiff::UpdateAt(index)
+                | WidgetDiff::RemoveAt(index) => {
+                    *index = remap(*index);
+                }
+                WidgetDiff::Move {
+                    old_index,
+                    new_index,
+                } => {
+                    *old_index = remap(*old_index);
+                    *new_index = remap(*new_index);
+                }
+                WidgetDiff::Replace | WidgetDiff::Pop | WidgetDiff::Clear => {}
+            }
+        }
+    }
+
     /// Adds an element at the front.
     pub fn push_front(&mut self, data: Data) {
         for elem in &self.data {
             elem.index.increment();
         }
-        let index = 0;
-        self.add_change(Change::new(index, ChangeType::Add));
-        let data = IndexedData::new(data, index);
-        self.data.push_front(data);
+        self.shift_diffs_for_insert(0);
+        let key = self.next_key();
+        self.data.push_front(IndexedData::new(data, 0, key));
+        self.diffs.borrow_mut().push(WidgetDiff::InsertAt(0));
     }
 
     /// Removes an element from the front.
     pub fn pop_front(&mut self) -> Option<Data> {
-        self.add_change(Change::new(0, ChangeType::Remove));
         let data = self.data.pop_front();
         for elem in &self.data {
             elem.index.decrement();
         }
+        if data.is_some() {
+            self.shift_diffs_for_remove(0);
+            self.diffs.borrow_mut().push(WidgetDiff::RemoveAt(0));
+        }
         data.map(|data| data.inner)
     }
 
@@ -187,128 +352,475 @@ where
                 elem.index.increment();
             }
         }
-        self.add_change(Change::new(index, ChangeType::Add));
-        let data = IndexedData::new(data, index);
-        self.data.insert(index, data);
+        self.shift_diffs_for_insert(index);
+        let key = self.next_key();
+        self.data.insert(index, IndexedData::new(data, index, key));
+        self.diffs.borrow_mut().push(WidgetDiff::InsertAt(index));
     }
 
     /// Removes an element at a given index.
     pub fn remove(&mut self, index: usize) -> Option<Data> {
-        self.add_change(Change::new(index, ChangeType::Remove));
         let data = self.data.remove(index);
         for elem in &self.data {
             if elem.index.current_index() > index {
                 elem.index.decrement();
             }
         }
+        if data.is_some() {
+            self.shift_diffs_for_remove(index);
+            self.diffs.borrow_mut().push(WidgetDiff::RemoveAt(index));
+        }
         data.map(|data| data.inner)
     }
 
+    /// Adjust the indices embedded in already-queued diffs so each one still refers
+    /// to the same logical item once the elements at `lo` and `hi` trade places,
+    /// without the elements between them moving at all.
+    fn shift_diffs_for_swap(&self, lo: usize, hi: usize) {
+        let remap = |index: usize| -> usize {
+            if index == lo {
+                hi
+            } else if index == hi {
+                lo
+            } else {
+                index
+            }
+        };
+
+        for diff in self.diffs.borrow_mut().iter_mut() {
+            match diff {
+                WidgetDiff::InsertAt(index)
+                | WidgetDiff::UpdateAt(index)
+                | WidgetDiff::RemoveAt(index) => {
+                    *index = remap(*index);
+                }
+                WidgetDiff::Move {
+                    old_index,
+                    new_index,
+                } => {
+                    *old_index = remap(*old_index);
+                    *new_index = remap(*new_index);
+                }
+                WidgetDiff::Replace | WidgetDiff::Pop | WidgetDiff::Clear => {}
+            }
+        }
+    }
+
+    /// Swaps the elements at indices `a` and `b`, relocating their existing widgets
+    /// instead of recreating them.
+    pub fn swap(&mut self, a: usize, b: usize) {
+        if a == b {
+            return;
+        }
+        let (lo, hi) = if a < b { (a, b) } else { (b, a) };
+
+        self.shift_diffs_for_swap(lo, hi);
+        self.data.swap(lo, hi);
+        self.data[lo].index.set(lo);
+        self.data[hi].index.set(hi);
+
+        let mut diffs = self.diffs.borrow_mut();
+        diffs.push(WidgetDiff::Move {
+            old_index: lo,
+            new_index: hi,
+        });
+        diffs.push(WidgetDiff::Move {
+            old_index: hi - 1,
+            new_index: lo,
+        });
+    }
+
+    /// Moves the element at `from` so that it ends up at `to`, shifting the elements
+    /// in between by one and relocating the existing widget instead of recreating it.
+    pub fn move_to(&mut self, from: usize, to: usize) {
+        if from == to {
+            return;
+        }
+        self.shift_diffs_for_move(from, to);
+        let elem = self.data.remove(from).expect("index out of bounds");
+        self.data.insert(to, elem);
+
+        let (lo, hi) = if from < to { (from, to) } else { (to, from) };
+        for (index, elem) in self.data.iter().enumerate().take(hi + 1).skip(lo) {
+            elem.index.set(index);
+        }
+
+        self.diffs.borrow_mut().push(WidgetDiff::Move {
+            old_index: from,
+            new_index: to,
+        });
+    }
+
+    /// Removes every element, dropping all of their widgets.
+    ///
+    /// Any diff queued earlier in the same batch referred to positions in the data
+    /// this just wiped out, so it is dropped along with the data instead of being
+    /// replayed against indices that no longer exist.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        let mut diffs = self.diffs.borrow_mut();
+        diffs.clear();
+        diffs.push(WidgetDiff::Clear);
+    }
+
+    /// Replace the entire contents of the [`FactoryVecDeque`], regenerating every widget.
+    ///
+    /// Any diff queued earlier in the same batch referred to positions in the data
+    /// this just replaced wholesale, so it is dropped instead of being replayed
+    /// against the new, unrelated contents.
+    pub fn replace(&mut self, values: impl IntoIterator<Item = Data>) {
+        self.data = values
+            .into_iter()
+            .enumerate()
+            .map(|(index, data)| {
+                let key = self.next_key;
+                self.next_key += 1;
+                IndexedData::new(data, index, FactoryKey(key))
+            })
+            .collect();
+        let mut diffs = self.diffs.borrow_mut();
+        diffs.clear();
+        diffs.push(WidgetDiff::Replace);
+    }
+
     /// Get a reference to data stored at `index`.
     pub fn get(&self, index: usize) -> &Data {
         &self.data[index].inner
     }
 
+    /// Get a reference to the data associated with `key`, if it is still present.
+    pub fn get_by_key(&self, key: FactoryKey) -> Option<&Data> {
+        self.data
+            .iter()
+            .find(|elem| elem.key == key)
+            .map(|elem| &elem.inner)
+    }
+
+    /// Remove the element identified by `key`, if it is still present.
+    pub fn remove_by_key(&mut self, key: FactoryKey) -> Option<Data> {
+        let index = self.data.iter().position(|elem| elem.key == key)?;
+        self.remove(index)
+    }
+
+    /// Get the stable [`FactoryKey`] of the element currently at `index`.
+    pub fn key_of(&self, index: usize) -> FactoryKey {
+        self.data[index].key
+    }
+
     /// Get a mutable reference to data stored at `index`.
     ///
     /// Assumes that the data will be modified and the corresponding widget
     /// needs to be updated.
     pub fn get_mut(&mut self, index: usize) -> &mut Data {
-        self.add_change(Change::new(index, ChangeType::Update));
+        self.diffs.borrow_mut().push(WidgetDiff::UpdateAt(index));
 
         &mut self.data[index].inner
     }
 
-    fn add_change(&mut self, change: Change) {
-        match change.ty {
-            ChangeType::Add => {
-                for elem in self.changes.borrow_mut().iter_mut() {
-                    if elem.index >= change.index {
-                        elem.index += 1;
-                    }
-                }
+    /// Apply an externally produced [`FactoryDiff`] to this collection, the same way
+    /// the matching method (for example [`push_back`](Self::push_back)) would.
+    pub fn apply_diff(&mut self, diff: FactoryDiff<Data>) {
+        match diff {
+            FactoryDiff::Replace(values) => self.replace(values),
+            FactoryDiff::InsertAt(index, data) => self.insert(index, data),
+            FactoryDiff::Push(data) => self.push_back(data),
+            FactoryDiff::UpdateAt(index, data) => *self.get_mut(index) = data,
+            FactoryDiff::RemoveAt(index) => {
+                self.remove(index);
             }
-            ChangeType::Remove => {
-                for elem in self.changes.borrow_mut().iter_mut() {
-                    if elem.index > change.index {
-                        elem.index -= 1;
-                    }
-                }
+            FactoryDiff::Pop => {
+                self.pop_back();
             }
-            _ => (),
+            FactoryDiff::Clear => self.clear(),
+            FactoryDiff::Move {
+                old_index,
+                new_index,
+            } => self.move_to(old_index, new_index),
         }
-        self.changes.borrow_mut().push(change);
-    }
-
-    fn compile_changes(&self) -> Vec<ChangeType> {
-        let mut change_map = vec![ChangeType::Unchanged; self.data.len() + 1];
-
-        for change in self.changes.borrow().iter() {
-            while change_map.len() < change.index {
-                change_map.push(ChangeType::Unchanged);
-            }
-            change_map[change.index].apply(change.ty);
-        }
-
-        change_map
     }
 }
 
 impl<Data, View> Factory<Data, View> for FactoryVecDeque<Data>
 where
     Data: FactoryPrototype<Factory = Self, View = View>,
+    Data::Root: Clone,
     View: FactoryView<Data::Root> + FactoryListView<Data::Root>,
 {
-    type Key = Rc<DynamicIndex>;
+    type Key = FactoryKey;
 
     fn generate(&self, view: &View, sender: Sender<Data::Msg>) {
-        let change_map = self.compile_changes();
-        for (index, change) in change_map.iter().enumerate() {
-            let mut widgets = self.widgets.borrow_mut();
-
-            dbg!(&change);
-            match change {
-                ChangeType::Unchanged => (),
-                ChangeType::Add => {
+        let mut widgets = self.widgets.borrow_mut();
+
+        for diff in self.diffs.borrow_mut().drain(..) {
+            match diff {
+                WidgetDiff::Replace => {
+                    Self::remove_all_fragments(&mut widgets, view);
+                    for (index, data) in self.data.iter().enumerate() {
+                        let widget = data.inner.generate(&data.index, sender.clone());
+                        Self::insert_fragment(&mut widgets, view, index, widget);
+                    }
+                }
+                WidgetDiff::InsertAt(index) => {
                     let data = &self.data[index];
                     let widget = data.inner.generate(&data.index, sender.clone());
-                    if widgets.is_empty() || index == 0 {
-                        view.push_front(Data::get_root(&widget));
-                    } else {
-                        view.insert_after(
-                            Data::get_root(&widget),
-                            Data::get_root(&widgets[index - 1]),
-                        );
-                    }
-                    widgets.insert(index, widget);
+                    Self::insert_fragment(&mut widgets, view, index, widget);
                 }
-                ChangeType::Update => {
+                WidgetDiff::UpdateAt(index) => {
                     let data = &self.data[index];
                     data.inner.update(&data.index, &widgets[index]);
                 }
-                ChangeType::Remove => {
-                    let widget = widgets.remove(index).unwrap();
-                    let remove_widget = Data::get_root(&widget);
-                    view.remove(remove_widget);
+                WidgetDiff::RemoveAt(index) => {
+                    Self::remove_fragment(&mut widgets, view, index);
                 }
-                ChangeType::Recreate => {
-                    let widget = widgets.pop_back().unwrap();
-                    let remove_widget = Data::get_root(&widget);
-                    view.remove(remove_widget);
-                    let data = &self.data[index];
-                    let widget = data.inner.generate(&data.index, sender.clone());
-                    if widgets.is_empty() || index == 0 {
-                        view.push_front(Data::get_root(&widget));
-                    } else {
-                        view.insert_after(
-                            Data::get_root(&widget),
-                            Data::get_root(&widgets[index - 1]),
-                        );
+                WidgetDiff::Pop => {
+                    if let Some(widget) = widgets.pop_back() {
+                        for root in Data::get_root(&widget).roots() {
+                            view.remove(root);
+                        }
+                    }
+                }
+                WidgetDiff::Clear => {
+                    Self::remove_all_fragments(&mut widgets, view);
+                }
+                WidgetDiff::Move {
+                    old_index,
+                    new_index,
+                } => {
+                    let widget = widgets.remove(old_index).unwrap();
+                    widgets.insert(new_index, widget);
+
+                    let mut after = Self::last_root_before(&widgets, new_index);
+                    for root in Data::get_root(&widgets[new_index]).roots() {
+                        match &after {
+                            Some(sibling) => view.move_after(root, sibling),
+                            None => view.move_front(root),
+                        }
+                        after = Some(root.clone());
                     }
-                    widgets.insert(index, widget);
                 }
             }
         }
-        self.changes.borrow_mut().clear();
     }
-}
\ No newline at end of file
+}
+
+impl<Data, View> FactoryVecDeque<Data>
+where
+    Data: FactoryPrototype<Factory = Self, View = View>,
+    Data::Root: Clone,
+    View: FactoryView<Data::Root> + FactoryListView<Data::Root>,
+{
+    /// The last root widget contributed by any of `widgets[..index]`, skipping back
+    /// over widgets whose fragment is empty so a sibling lookup doesn't stop at the
+    /// first zero-root item it meets.
+    fn last_root_before(widgets: &VecDeque<Data::Widgets>, index: usize) -> Option<Data::Root> {
+        widgets
+            .iter()
+            .take(index)
+            .rev()
+            .find_map(|widget| Data::get_root(widget).roots().last().cloned())
+    }
+
+    /// Insert `widget` into `widgets` at `index`, placing its fragment's roots right
+    /// after the last preceding widget that actually contributed a root (or at the
+    /// front of the view if there is none).
+    fn insert_fragment(
+        widgets: &mut VecDeque<Data::Widgets>,
+        view: &View,
+        index: usize,
+        widget: Data::Widgets,
+    ) {
+        let mut after = Self::last_root_before(widgets, index);
+
+        for root in Data::get_root(&widget).roots() {
+            match &after {
+                Some(sibling) => view.insert_after(root, sibling),
+                None => view.push_front(root),
+            }
+            after = Some(root.clone());
+        }
+
+        widgets.insert(index, widget);
+    }
+
+    /// Remove the widget at `index` from `widgets`, removing every root of its fragment.
+    fn remove_fragment(widgets: &mut VecDeque<Data::Widgets>, view: &View, index: usize) {
+        if let Some(widget) = widgets.remove(index) {
+            for root in Data::get_root(&widget).roots() {
+                view.remove(root);
+            }
+        }
+    }
+
+    /// Remove every widget, along with every root of every fragment.
+    fn remove_all_fragments(widgets: &mut VecDeque<Data::Widgets>, view: &View) {
+        for widget in widgets.drain(..) {
+            for root in Data::get_root(&widget).roots() {
+                view.remove(root);
+            }
+        }
+    }
+
+    /// Subscribe `this` to a [`Stream`] of [`FactoryDiff`]s, applying each one as it
+    /// arrives via [`apply_diff`](Self::apply_diff) and re-running
+    /// [`Factory::generate`] on the GTK main context afterwards.
+    ///
+    /// This lets an observable data source (for example a background task reading
+    /// from a channel or a remote source) drive a [`FactoryVecDeque`] directly: each
+    /// item off the stream both mutates `self.data` and regenerates the view, so the
+    /// caller never has to hold `this` itself to call `push_back`/`remove`/etc.
+    ///
+    /// Dropping the returned [`Subscription`] cancels the binding.
+    pub fn bind_stream<S>(
+        this: Rc<RefCell<Self>>,
+        view: View,
+        sender: Sender<Data::Msg>,
+        mut stream: S,
+    ) -> Subscription
+    where
+        Data: 'static,
+        View: 'static,
+        S: Stream<Item = FactoryDiff<Data>> + Unpin + 'static,
+    {
+        let handle = glib::MainContext::ref_thread_default().spawn_local(async move {
+            while let Some(diff) = stream.next().await {
+                this.borrow_mut().apply_diff(diff);
+                this.borrow().generate(&view, sender.clone());
+            }
+        });
+
+        Subscription { handle }
+    }
+}
+
+/// A handle to a [`FactoryVecDeque::bind_stream`] subscription.
+///
+/// Dropping this cancels the subscription, so no further diffs are applied.
+#[derive(Debug)]
+pub struct Subscription {
+    handle: glib::JoinHandle<()>,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct TestItem(i32);
+
+    impl FactoryPrototype for TestItem {
+        type Factory = FactoryVecDeque<Self>;
+        type View = ();
+        type Msg = ();
+        type Widgets = ();
+        type Root = ();
+
+        fn generate(&self, _index: &DynamicIndex, _sender: Sender<()>) -> Self::Widgets {}
+
+        fn update(&self, _index: &DynamicIndex, _widgets: &Self::Widgets) {}
+
+        fn get_root(_widgets: &Self::Widgets) -> FactoryFragment<Self::Root> {
+            FactoryFragment::empty()
+        }
+    }
+
+    impl FactoryView<()> for () {
+        fn remove(&self, _widget: &()) {}
+    }
+
+    impl FactoryListView<()> for () {
+        fn push_front(&self, _widget: &()) {}
+        fn insert_after(&self, _widget: &(), _other: &()) {}
+        fn move_front(&self, _widget: &()) {}
+        fn move_after(&self, _widget: &(), _other: &()) {}
+    }
+
+    fn deque_of(values: impl IntoIterator<Item = i32>) -> FactoryVecDeque<TestItem> {
+        let mut deque = FactoryVecDeque::new();
+        for value in values {
+            deque.push_back(TestItem(value));
+        }
+        // The pushes above each queue their own `InsertAt` diff; tests care about
+        // the diffs a later mutation queues, not these setup ones.
+        deque.diffs.borrow_mut().clear();
+        deque
+    }
+
+    #[test]
+    fn move_to_shifts_an_already_queued_update() {
+        let mut deque = deque_of(0..5);
+
+        deque.get_mut(3); // queues UpdateAt(3), naming the item currently at index 3
+        deque.move_to(1, 4); // that item ends up at index 2
+
+        assert!(matches!(deque.diffs.borrow()[0], WidgetDiff::UpdateAt(2)));
+    }
+
+    #[test]
+    fn swap_shifts_an_already_queued_update() {
+        let mut deque = deque_of(0..5);
+
+        deque.get_mut(1); // queues UpdateAt(1)
+        deque.swap(1, 3);
+
+        assert!(matches!(deque.diffs.borrow()[0], WidgetDiff::UpdateAt(3)));
+    }
+
+    #[test]
+    fn insert_shifts_already_queued_diffs() {
+        let mut deque = deque_of(0..5);
+
+        deque.get_mut(2); // queues UpdateAt(2)
+        deque.insert(1, TestItem(9));
+
+        assert!(matches!(deque.diffs.borrow()[0], WidgetDiff::UpdateAt(3)));
+    }
+
+    #[test]
+    fn remove_shifts_already_queued_diffs() {
+        let mut deque = deque_of(0..5);
+
+        deque.get_mut(3); // queues UpdateAt(3)
+        deque.remove(1);
+
+        assert!(matches!(deque.diffs.borrow()[0], WidgetDiff::UpdateAt(2)));
+    }
+
+    #[test]
+    fn clear_drops_diffs_queued_earlier_in_the_same_batch() {
+        let mut deque = deque_of(0..5);
+
+        deque.get_mut(0); // queues UpdateAt(0), which `clear` makes meaningless
+        deque.clear();
+
+        let diffs = deque.diffs.borrow();
+        assert_eq!(diffs.len(), 1);
+        assert!(matches!(diffs[0], WidgetDiff::Clear));
+    }
+
+    #[test]
+    fn replace_drops_diffs_queued_earlier_in_the_same_batch() {
+        let mut deque = deque_of(0..5);
+
+        deque.get_mut(0); // queues UpdateAt(0), which `replace` makes meaningless
+        deque.replace(vec![TestItem(9)]);
+
+        let diffs = deque.diffs.borrow();
+        assert_eq!(diffs.len(), 1);
+        assert!(matches!(diffs[0], WidgetDiff::Replace));
+    }
+
+    #[test]
+    fn apply_diff_push_mutates_the_data() {
+        let mut deque = deque_of(0..3);
+
+        deque.apply_diff(FactoryDiff::Push(TestItem(9)));
+
+        assert_eq!(deque.get(3), &TestItem(9));
+    }
+}
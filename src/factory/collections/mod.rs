@@ -0,0 +1,4 @@
+//! [`Factory`](super::Factory) implementations for common container shapes.
+
+pub mod factory_vec_deque;
+pub use factory_vec_deque::FactoryVecDeque;
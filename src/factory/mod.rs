@@ -0,0 +1,99 @@
+//! Traits for widget containers whose contents are generated from a data collection.
+
+pub mod collections;
+
+use gtk::glib::Sender;
+
+/// A collection that can generate and update the widgets of a [`FactoryView`] to
+/// match its own, usually richer, data.
+pub trait Factory<Data, View>
+where
+    Data: FactoryPrototype<Factory = Self, View = View>,
+{
+    /// The type used to uniquely and stably identify one entry of the collection.
+    type Key;
+
+    /// Generate, update, move or remove the widgets of `view` to match the current
+    /// state of the collection.
+    fn generate(&self, view: &View, sender: Sender<Data::Msg>);
+}
+
+/// A type that can be turned into the widgets stored in a [`Factory`].
+pub trait FactoryPrototype: Sized {
+    /// The [`Factory`] this prototype's instances are collected in.
+    type Factory: Factory<Self, Self::View>;
+
+    /// The [`FactoryView`] the generated widgets are inserted into.
+    type View;
+
+    /// The message type emitted by the generated widgets.
+    type Msg;
+
+    /// The widgets generated for a single instance of this prototype.
+    type Widgets;
+
+    /// The root widget type of the generated widgets.
+    type Root;
+
+    /// Generate the widgets for one element of the [`Factory`].
+    fn generate(
+        &self,
+        index: &collections::factory_vec_deque::DynamicIndex,
+        sender: Sender<Self::Msg>,
+    ) -> Self::Widgets;
+
+    /// Update `widgets` to match the current state of `self`.
+    fn update(&self, index: &collections::factory_vec_deque::DynamicIndex, widgets: &Self::Widgets);
+
+    /// The root widgets generated for `widgets`, in display order.
+    fn get_root(widgets: &Self::Widgets) -> collections::factory_vec_deque::FactoryFragment<Self::Root>;
+}
+
+/// A view that a [`Factory`] can insert and remove root widgets from.
+pub trait FactoryView<Root> {
+    /// Remove `widget` from the view.
+    fn remove(&self, widget: &Root);
+}
+
+/// A [`FactoryView`] whose widgets form a single ordered list, so a [`Factory`] can
+/// insert new widgets at a specific position and relocate existing ones when its
+/// data is reordered, instead of only ever appending or destroying and rebuilding.
+pub trait FactoryListView<Root>: FactoryView<Root> {
+    /// Insert `widget` at the front of the view.
+    fn push_front(&self, widget: &Root);
+
+    /// Insert `widget` directly after `other`.
+    fn insert_after(&self, widget: &Root, other: &Root);
+
+    /// Move the already-inserted `widget` to the front of the view, without
+    /// recreating it.
+    fn move_front(&self, widget: &Root);
+
+    /// Move the already-inserted `widget` so that it directly follows `other`,
+    /// without recreating it.
+    fn move_after(&self, widget: &Root, other: &Root);
+}
+
+impl FactoryView<gtk::Widget> for gtk::Box {
+    fn remove(&self, widget: &gtk::Widget) {
+        gtk::prelude::BoxExt::remove(self, widget);
+    }
+}
+
+impl FactoryListView<gtk::Widget> for gtk::Box {
+    fn push_front(&self, widget: &gtk::Widget) {
+        self.prepend(widget);
+    }
+
+    fn insert_after(&self, widget: &gtk::Widget, other: &gtk::Widget) {
+        self.insert_child_after(widget, Some(other));
+    }
+
+    fn move_front(&self, widget: &gtk::Widget) {
+        self.reorder_child_after(widget, None::<&gtk::Widget>);
+    }
+
+    fn move_after(&self, widget: &gtk::Widget, other: &gtk::Widget) {
+        self.reorder_child_after(widget, Some(other));
+    }
+}